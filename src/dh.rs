@@ -23,17 +23,18 @@
 //! use ring::rand;
 //! let param = &DHPARAM_FFDHE2048;
 //! let rng = rand::SystemRandom::new();
-//! let my_private_key = DhContext::new(param, &rng);
-//! let my_public_key = my_private_key.compute_public_key();
+//! let my_private_key = DhContext::new(param, &rng).unwrap();
+//! let mut my_public_key_buffer = [0u8; MAX_PUBLIC_KEY_LEN];
+//! let my_public_key = my_private_key.compute_public_key(&mut my_public_key_buffer[..]).unwrap();
 //!
-//! let peer_public_key_buffer = [0u8; MAX_PUBLIC_KEY_LEN];
-//! let peer_private_key = DhContext::new(param, &rng);
+//! let peer_private_key = DhContext::new(param, &rng).unwrap();
+//! let mut peer_public_key_buffer = [0u8; MAX_PUBLIC_KEY_LEN];
 //! let peer_public_key = {
-//!     peer_private_key.compute_public_key(&mut peer_public_key_buffer[..])
+//!     peer_private_key.compute_public_key(&mut peer_public_key_buffer[..]).unwrap()
 //! };
 //!
-//! let secret_key1 = my_private_key.compute_shared_key(&peer_public_key);
-//! let secret_key2 = peer_private_key.compute_shared_key(&my_public_key);
+//! let secret_key1 = my_private_key.compute_shared_key(&peer_public_key).unwrap();
+//! let secret_key2 = peer_private_key.compute_shared_key(&my_public_key).unwrap();
 //! ```
 
 extern crate alloc;
@@ -48,6 +49,19 @@ pub const MAX_PUBLIC_KEY_LEN : usize = 1024usize;
 /// MAX_SECRET_LEN for DH
 pub const MAX_SECRET_LEN : usize = 64usize;
 
+/// Errors produced by the DH key-agreement API.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The group parameters (prime/base/secret length) are malformed.
+    InvalidParameter,
+    /// The peer public key is out of range, not in the subgroup, or malformed.
+    InvalidPublicKey,
+    /// The caller-supplied output buffer is smaller than the public key.
+    OutputBufferTooShort,
+    /// The random number generator failed.
+    RngFailure,
+}
+
 /// Indicates the dhparam
 pub struct DhParam {
     /// prime
@@ -250,12 +264,127 @@ pub fn from_hex(hex_str: &str) -> Result<Vec<u8>, &'static str> {
     Ok(result)
 }
 
+// Big-endian magnitude view with leading zero octets removed.
+fn trim_be(bytes: &[u8]) -> &[u8] {
+    let mut i = 0;
+    while i < bytes.len() && bytes[i] == 0 {
+        i += 1;
+    }
+    &bytes[i..]
+}
+
+// Compare two big-endian magnitudes.
+fn cmp_be(a: &[u8], b: &[u8]) -> core::cmp::Ordering {
+    let a = trim_be(a);
+    let b = trim_be(b);
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+// (p - 1) / 2 for an odd prime p, computed as a right shift by one bit over
+// the big-endian octets.
+fn halve_be(bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut carry = 0u8;
+    for &b in bytes {
+        out.push((carry << 7) | (b >> 1));
+        carry = b & 1;
+    }
+    out
+}
+
+// p - 2, used for the `y <= p - 2` bound. p is a safe prime so no borrow
+// escapes the low octets, but the loop handles the general case anyway.
+fn sub_two_be(bytes: &[u8]) -> Vec<u8> {
+    let mut out = bytes.to_vec();
+    let mut borrow = 2i16;
+    for b in out.iter_mut().rev() {
+        let v = *b as i16 - borrow;
+        if v < 0 {
+            *b = (v + 256) as u8;
+            borrow = 1;
+        } else {
+            *b = v as u8;
+            borrow = 0;
+            break;
+        }
+    }
+    out
+}
+
+// n - 1 over big-endian octets (n > 0).
+fn sub_one_be(bytes: &[u8]) -> Vec<u8> {
+    let mut out = bytes.to_vec();
+    for b in out.iter_mut().rev() {
+        if *b == 0 {
+            *b = 0xff;
+        } else {
+            *b -= 1;
+            break;
+        }
+    }
+    out
+}
+
+// Bit length of a big-endian magnitude.
+fn bit_len_be(bytes: &[u8]) -> usize {
+    let b = trim_be(bytes);
+    match b.first() {
+        None => 0,
+        Some(&hi) => (b.len() - 1) * 8 + (8 - hi.leading_zeros() as usize),
+    }
+}
+
+// Whether a big-endian magnitude equals 1.
+fn is_one_be(bytes: &[u8]) -> bool {
+    trim_be(bytes) == [1u8]
+}
+
+// Whether a big-endian magnitude equals 0.
+fn is_zero_be(bytes: &[u8]) -> bool {
+    trim_be(bytes).is_empty()
+}
+
+// Overwrite a secret buffer with a volatile store loop so the compiler cannot
+// optimize the wipe away, followed by a fence to order it before the memory is
+// released. This is the same discipline the `zeroize` crate applies to private
+// scalars; we inline it to avoid pulling in a dependency.
+fn zeroize(buf: &mut [u8]) {
+    for b in buf.iter_mut() {
+        unsafe { core::ptr::write_volatile(b, 0u8) };
+    }
+    core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::SeqCst);
+}
+
+/// Shared secret that zeroizes its heap buffer on drop so key material does not
+/// linger in freed pages.
+pub struct SharedSecret(Vec<u8>);
+
+impl SharedSecret {
+    /// the raw shared secret octets
+    pub fn as_slice(&self) -> &[u8] {
+        self.0.as_slice()
+    }
+}
+
+impl Drop for SharedSecret {
+    fn drop(&mut self) {
+        zeroize(&mut self.0);
+    }
+}
+
 /// Dhcontext
 /// Dh
 pub struct DhContext {
-    a: PrivateExponent<()>,                // private key
+    a: Vec<u8>,                            // private exponent, big-endian
     ap: Elem<()>,                          // public key
     p: Modulus<()>,                        // prime
+    p_bytes: Vec<u8>,                      // prime, big-endian, for peer-key checks
+}
+
+impl Drop for DhContext {
+    fn drop(&mut self) {
+        zeroize(&mut self.a);
+    }
 }
 
 fn into_encoded<T>(a: Elem<T, Unencoded>, m: &Modulus<T>) -> Elem<T, R> {
@@ -264,58 +393,297 @@ fn into_encoded<T>(a: Elem<T, Unencoded>, m: &Modulus<T>) -> Elem<T, R> {
 
 impl DhContext {
     /// new a DhContext
-    pub fn new(param: &'static DhParam, rng: &dyn rand::SecureRandom) -> Option<Self> {
-        let mut a = [0u8; MAX_SECRET_LEN];
+    pub fn new(param: &'static DhParam, rng: &dyn rand::SecureRandom) -> Result<Self, Error> {
+        if param.secret_len == 0 || param.secret_len > MAX_SECRET_LEN {
+            return Err(Error::InvalidParameter);
+        }
+        let mut buf = [0u8; MAX_SECRET_LEN];
 
-        let a = &mut a[0..param.secret_len];
-        rng.fill(a).ok()?;
+        let secret = &mut buf[0..param.secret_len];
+        rng.fill(secret).map_err(|_| Error::RngFailure)?;
 
-        a[param.secret_len-1] |= 1;
+        secret[param.secret_len-1] |= 1;
+
+        let ctx = Self::from_param_and_secret(param, secret);
+
+        // Keep only the raw private exponent; wipe the stack buffer it came
+        // from so the secret is not left on a reusable frame.
+        zeroize(secret);
+        ctx
+    }
 
+    /// Build a context from a caller-supplied private exponent instead of
+    /// drawing one from the RNG. This reconstructs a session from stored key
+    /// material and makes reproducible test vectors possible. The private key
+    /// must be a valid exponent for `param` (odd, less than the prime).
+    pub fn from_private_key(param: &'static DhParam, private_key: &[u8]) -> Result<Self, Error> {
+        Self::from_param_and_secret(param, private_key)
+    }
+
+    /// Draw a fresh private exponent for runtime-supplied (e.g. server
+    /// negotiated) parameters.
+    ///
+    /// # Safety of peer-supplied parameters
+    ///
+    /// This constructor performs **no** primality or safe-prime checking on
+    /// `param`. Running key agreement over unvalidated parameters taken from an
+    /// untrusted peer is unsafe: a maliciously weak or composite group can
+    /// confine or leak the shared secret. Callers handling peer-negotiated
+    /// groups MUST call [`DhParamBytes::validate`] and only proceed if it
+    /// returns `Ok(())`.
+    pub fn from_param_bytes(param: &DhParamBytes, rng: &dyn rand::SecureRandom) -> Result<Self, Error> {
+        if param.secret_len == 0 || param.secret_len > MAX_SECRET_LEN {
+            return Err(Error::InvalidParameter);
+        }
+        let mut buf = [0u8; MAX_SECRET_LEN];
+        let secret = &mut buf[0..param.secret_len];
+        rng.fill(secret).map_err(|_| Error::RngFailure)?;
+        secret[param.secret_len-1] |= 1;
+
+        let ctx = Self::build(param.p.clone(), &param.g, secret);
+        zeroize(secret);
+        ctx
+    }
+
+    // Shared constructor path for both the random and the caller-supplied
+    // private exponent of a static group.
+    fn from_param_and_secret(param: &DhParam, secret: &[u8]) -> Result<Self, Error> {
+        let p_bytes = from_hex(param.p).map_err(|_| Error::InvalidParameter)?;
+        let g_bytes = from_hex(param.g).map_err(|_| Error::InvalidParameter)?;
+        Self::build(p_bytes, &g_bytes, secret)
+    }
+
+    // Derive the public key `g^a mod p` from raw big-endian parameter octets
+    // and retain the prime for later peer-key validation.
+    fn build(p_bytes: Vec<u8>, g_bytes: &[u8], secret: &[u8]) -> Result<Self, Error> {
         let p = {
-            let inputhex = &from_hex(param.p).ok()?;
-            let input = untrusted::Input::from(inputhex);
+            let input = untrusted::Input::from(&p_bytes);
             let v = Modulus::<()>::from_be_bytes_with_bit_length(
-            input).ok()?;
+            input).map_err(|_| Error::InvalidParameter)?;
             v.0
         };
 
-        let a = {
-            PrivateExponent::<()>::from_be_bytes_padded(untrusted::Input::from(a), &p).ok()?
-        };
+        let exponent = PrivateExponent::<()>::from_be_bytes_padded(
+            untrusted::Input::from(secret), &p).map_err(|_| Error::InvalidParameter)?;
 
         let g: Elem<(),Unencoded> =  Elem::<()>::from_be_bytes_padded(
-            untrusted::Input::from(
-                &from_hex(param.g).unwrap()
-        ),&p).ok()?;
+            untrusted::Input::from(g_bytes), &p).map_err(|_| Error::InvalidParameter)?;
 
         let g = into_encoded(g, &p);
-        let ap = elem_exp_consttime(g, &a, &p).ok()?;
-        Some(DhContext {a, ap, p})
+        let ap = elem_exp_consttime(g, &exponent, &p).map_err(|_| Error::InvalidParameter)?;
+
+        let a = secret.to_vec();
+        Ok(DhContext {a, ap, p, p_bytes})
+    }
+
+    // Validate a peer public value `y` before raising it to our private
+    // exponent. The `DHPARAM_FFDHE*` groups are RFC 7919 safe primes
+    // (p = 2q + 1, g = 2 generating the order-q subgroup of quadratic
+    // residues), so a well-formed `y` must satisfy `2 <= y <= p - 2` and lie
+    // in that subgroup, i.e. `y^q mod p == 1`. Rejecting everything else shuts
+    // out the 0/1/p-1 and small-subgroup confinement attacks.
+    fn validate_peer_public_key(&self, peer_public_key: &[u8]) -> Result<(), Error> {
+        if cmp_be(peer_public_key, &[2]) == core::cmp::Ordering::Less {
+            return Err(Error::InvalidPublicKey);
+        }
+        if cmp_be(peer_public_key, &sub_two_be(&self.p_bytes))
+            == core::cmp::Ordering::Greater
+        {
+            return Err(Error::InvalidPublicKey);
+        }
+
+        let q = halve_be(&self.p_bytes);
+        let q = PrivateExponent::<()>::from_be_bytes_padded(
+            untrusted::Input::from(&q), &self.p).map_err(|_| Error::InvalidPublicKey)?;
+        let y = Elem::<()>::from_be_bytes_padded(
+            untrusted::Input::from(peer_public_key), &self.p).map_err(|_| Error::InvalidPublicKey)?;
+        let y = into_encoded(y, &self.p);
+        let residue = elem_exp_consttime(y, &q, &self.p).map_err(|_| Error::InvalidPublicKey)?;
+        if !is_one_be(&residue.to_bytes_be()) {
+            return Err(Error::InvalidPublicKey);
+        }
+        Ok(())
     }
 
     /// get public key bytes
-    pub fn compute_public_key<'a>(&self, buffer: &'a mut [u8]) -> &'a [u8] {
+    pub fn compute_public_key<'a>(&self, buffer: &'a mut [u8]) -> Result<&'a [u8], Error> {
         let pubkey = self.ap.to_bytes_be();
         let pubkey_slice = pubkey.as_slice();
         let len = pubkey.len();
+        if buffer.len() < len {
+            return Err(Error::OutputBufferTooShort);
+        }
         let res = &mut buffer[0..len];
         res.copy_from_slice(pubkey_slice);
-        res
+        Ok(res)
     }
 
     /// get share key bytes
-    pub fn compute_shared_key(&self, _peer_public_key: &[u8]) -> Vec<u8> {
-        // let peer_public_key = BigUint::from_bytes_le(peer_public_key);
-        // peer_public_key.modpow(&self.a, &self.p).to_bytes_le()
+    ///
+    /// Returns `Error::InvalidPublicKey` if the peer public key fails
+    /// validation (out of range or outside the order-q subgroup) or if the
+    /// resulting shared secret is a degenerate 0 or 1.
+    pub fn compute_shared_key(&self, peer_public_key: &[u8]) -> Result<SharedSecret, Error> {
+        self.validate_peer_public_key(peer_public_key)?;
+
         let p = {&self.p};
-        let a = {&self.a};
+        let a = PrivateExponent::<()>::from_be_bytes_padded(
+            untrusted::Input::from(&self.a), p).map_err(|_| Error::InvalidParameter)?;
         let peer_public_key: Elem<(),Unencoded> = Elem::<()>::from_be_bytes_padded(
-            untrusted::Input::from(_peer_public_key), p).unwrap();
+            untrusted::Input::from(peer_public_key), p).map_err(|_| Error::InvalidPublicKey)?;
+
+        let peer_public_key = into_encoded(peer_public_key, p);
+        let r = elem_exp_consttime(peer_public_key, &a, p).map_err(|_| Error::InvalidPublicKey)?;
+        let secret = r.to_bytes_be();
+        if is_zero_be(&secret) || is_one_be(&secret) {
+            return Err(Error::InvalidPublicKey);
+        }
+        Ok(SharedSecret(secret))
+    }
+}
+
+// Square x (big-endian) modulo m, routing through to_bytes_be/from_be_bytes so
+// no intermediate Elem has to be cloned.
+fn square_mod(x_be: &[u8], m: &Modulus<()>) -> Result<Vec<u8>, Error> {
+    let a = Elem::<()>::from_be_bytes_padded(untrusted::Input::from(x_be), m)
+        .map_err(|_| Error::InvalidParameter)?;
+    let b = Elem::<()>::from_be_bytes_padded(untrusted::Input::from(x_be), m)
+        .map_err(|_| Error::InvalidParameter)?;
+    let a = into_encoded(a, m);
+    Ok(elem_mul(&a, b, m).to_bytes_be())
+}
+
+// Number of independent Miller–Rabin rounds. Bases are drawn from the supplied
+// CSPRNG rather than a fixed public set, so an adversary cannot precompute a
+// composite that is a strong pseudoprime to a known list of witnesses
+// (Arnault-style constructions).
+const MR_ROUNDS: usize = 16;
+
+// Draw a random witness `w` with `2 <= w <= n - 2` from `rng`.
+fn random_witness(n: &[u8], rng: &dyn rand::SecureRandom) -> Result<Vec<u8>, Error> {
+    let n_minus_1 = sub_one_be(n);
+    loop {
+        let mut buf = alloc::vec![0u8; n.len()];
+        rng.fill(&mut buf).map_err(|_| Error::RngFailure)?;
+        let w = trim_be(&buf);
+        if cmp_be(w, &[2]) != core::cmp::Ordering::Less
+            && cmp_be(w, &n_minus_1) == core::cmp::Ordering::Less
+        {
+            return Ok(w.to_vec());
+        }
+    }
+}
+
+// Miller–Rabin probable-prime test for a big-endian candidate `n`, using
+// `MR_ROUNDS` witness bases drawn from `rng`.
+fn is_probable_prime(n_be: &[u8], rng: &dyn rand::SecureRandom) -> Result<bool, Error> {
+    let n = trim_be(n_be);
+    if cmp_be(n, &[2]) == core::cmp::Ordering::Less {
+        return Ok(false);
+    }
+    if n[n.len() - 1] & 1 == 0 {
+        return Ok(n == [2u8]);
+    }
+    // 3 has an empty witness range (2..=n-2) but is prime.
+    if cmp_be(n, &[3]) == core::cmp::Ordering::Equal {
+        return Ok(true);
+    }
+
+    let m = Modulus::<()>::from_be_bytes_with_bit_length(untrusted::Input::from(n))
+        .map_err(|_| Error::InvalidParameter)?.0;
+
+    // n - 1 = 2^s * d with d odd.
+    let n_minus_1 = sub_one_be(n);
+    let mut d = n_minus_1.clone();
+    let mut s = 0usize;
+    while d[d.len() - 1] & 1 == 0 {
+        d = halve_be(&d);
+        s += 1;
+    }
 
-        let peer_public_key = into_encoded(peer_public_key, &p);
-        let r = elem_exp_consttime(peer_public_key, a, p).unwrap();
-        r.to_bytes_be()
+    for _ in 0..MR_ROUNDS {
+        let w = random_witness(n, rng)?;
+        let d_exp = PrivateExponent::<()>::from_be_bytes_padded(
+            untrusted::Input::from(&d), &m).map_err(|_| Error::InvalidParameter)?;
+        let base = Elem::<()>::from_be_bytes_padded(
+            untrusted::Input::from(&w), &m).map_err(|_| Error::InvalidParameter)?;
+        let base = into_encoded(base, &m);
+        let x = elem_exp_consttime(base, &d_exp, &m).map_err(|_| Error::InvalidParameter)?;
+
+        let mut xb = x.to_bytes_be();
+        if is_one_be(&xb) || cmp_be(&xb, &n_minus_1) == core::cmp::Ordering::Equal {
+            continue;
+        }
+        let mut maybe_prime = false;
+        for _ in 0..s.saturating_sub(1) {
+            xb = square_mod(&xb, &m)?;
+            if cmp_be(&xb, &n_minus_1) == core::cmp::Ordering::Equal {
+                maybe_prime = true;
+                break;
+            }
+        }
+        if !maybe_prime {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// DH parameters built at runtime from big-endian octet strings — e.g. a
+/// finite-field group negotiated by a TLS peer — rather than one of the fixed
+/// `DHPARAM_FFDHE*` constants.
+///
+/// This is a separate type from [`DhParam`] by necessity: `DhParam` stores its
+/// prime and base as `&'static str` hex so the `DHPARAM_FFDHE*` statics stay
+/// zero-allocation, which cannot hold caller-owned runtime bytes. `DhParamBytes`
+/// owns its octets instead; `from_be_bytes` is the runtime counterpart of the
+/// static hex constants.
+pub struct DhParamBytes {
+    p: Vec<u8>,
+    g: Vec<u8>,
+    secret_len: usize,
+}
+
+impl DhParamBytes {
+    /// Build runtime parameters from big-endian prime/base octets.
+    pub fn from_be_bytes(p: &[u8], g: &[u8], secret_len: usize) -> Self {
+        DhParamBytes { p: p.to_vec(), g: g.to_vec(), secret_len }
+    }
+
+    /// Reject maliciously weak or malformed parameters: `p` must be at least
+    /// `min_bits` long, `p` and `q = (p-1)/2` must both be prime (safe prime),
+    /// and `g` must generate the order-q subgroup (`g != 0,1` and
+    /// `g^q mod p == 1`). The primality test draws its witnesses from `rng`.
+    pub fn validate(&self, min_bits: usize, rng: &dyn rand::SecureRandom) -> Result<(), Error> {
+        if bit_len_be(&self.p) < min_bits {
+            return Err(Error::InvalidParameter);
+        }
+        if !is_probable_prime(&self.p, rng)? {
+            return Err(Error::InvalidParameter);
+        }
+        let q = halve_be(&self.p); // (p - 1) / 2 for odd p
+        if !is_probable_prime(&q, rng)? {
+            return Err(Error::InvalidParameter);
+        }
+        if is_zero_be(&self.g)
+            || is_one_be(&self.g)
+            || cmp_be(&self.g, &self.p) != core::cmp::Ordering::Less
+        {
+            return Err(Error::InvalidParameter);
+        }
+
+        let m = Modulus::<()>::from_be_bytes_with_bit_length(untrusted::Input::from(&self.p))
+            .map_err(|_| Error::InvalidParameter)?.0;
+        let q_exp = PrivateExponent::<()>::from_be_bytes_padded(
+            untrusted::Input::from(&q), &m).map_err(|_| Error::InvalidParameter)?;
+        let g = Elem::<()>::from_be_bytes_padded(
+            untrusted::Input::from(&self.g), &m).map_err(|_| Error::InvalidParameter)?;
+        let g = into_encoded(g, &m);
+        let residue = elem_exp_consttime(g, &q_exp, &m).map_err(|_| Error::InvalidParameter)?;
+        if !is_one_be(&residue.to_bytes_be()) {
+            return Err(Error::InvalidParameter);
+        }
+        Ok(())
     }
 }
 
@@ -325,12 +693,12 @@ pub fn agree_ephemeral<F, R>(
     my_private_key: DhContext,
     peer_public_key: &[u8],
     kdf: F,
-) -> Option<R>
+) -> Result<R, Error>
 where
-    F: FnOnce(&[u8]) -> Option<R>
+    F: FnOnce(&[u8]) -> R
 {
-    let secret_key = my_private_key.compute_shared_key(peer_public_key);
-    kdf(secret_key.as_slice())
+    let secret_key = my_private_key.compute_shared_key(peer_public_key)?;
+    Ok(kdf(secret_key.as_slice()))
 }
 
 #[cfg(test)]
@@ -343,17 +711,17 @@ mod tests {
         let rng = rand::SystemRandom::new();
         let my_private_key = DhContext::new(param, &rng).unwrap();
         let mut my_public_key_buffer = [0; MAX_PUBLIC_KEY_LEN];
-        let my_public_key = my_private_key.compute_public_key(&mut my_public_key_buffer[..]);
+        let my_public_key = my_private_key.compute_public_key(&mut my_public_key_buffer[..]).unwrap();
 
         let peer_private_key = DhContext::new(param, &rng).unwrap();
         let mut peer_public_key_buffer = [0; MAX_PUBLIC_KEY_LEN];
         let peer_public_key = {
-            peer_private_key.compute_public_key(&mut peer_public_key_buffer[..])
+            peer_private_key.compute_public_key(&mut peer_public_key_buffer[..]).unwrap()
         };
 
-        let secret_key1 = my_private_key.compute_shared_key(&peer_public_key);
-        let secret_key2 = peer_private_key.compute_shared_key(&my_public_key);
-        assert_eq!(secret_key1, secret_key2);
+        let secret_key1 = my_private_key.compute_shared_key(&peer_public_key).unwrap();
+        let secret_key2 = peer_private_key.compute_shared_key(&my_public_key).unwrap();
+        assert_eq!(secret_key1.as_slice(), secret_key2.as_slice());
     }
 
     #[test]
@@ -365,7 +733,7 @@ mod tests {
         let mut peer_public_key_buffer = [0u8; MAX_PUBLIC_KEY_LEN];
         let peer_public_key = {
             let peer_private_key = DhContext::new(param, &rng).unwrap();
-            peer_private_key.compute_public_key(&mut peer_public_key_buffer[..])
+            peer_private_key.compute_public_key(&mut peer_public_key_buffer[..]).unwrap()
         };
 
         let _ = agree_ephemeral(my_private_key, peer_public_key,|_shared_key| {
@@ -373,4 +741,204 @@ mod tests {
             Some(())
         });
     }
+
+    // A deterministic private exponent of the length `param` expects: an odd
+    // value well below the prime, derived from a fixed seed so the vectors are
+    // reproducible across runs.
+    fn fixed_private_key(param: &DhParam, seed: u8) -> alloc::vec::Vec<u8> {
+        let mut v = alloc::vec![0u8; param.secret_len];
+        for (i, b) in v.iter_mut().enumerate() {
+            *b = seed ^ (i as u8);
+        }
+        v[param.secret_len - 1] |= 1;
+        v
+    }
+
+    // `from_private_key` is a pure function of (param, private_key): the same
+    // inputs must always reproduce the same public key `y`.
+    #[test]
+    fn test_from_private_key_reproducible() {
+        for param in [&DHPARAM_FFDHE2048, &DHPARAM_FFDHE3072] {
+            let x = fixed_private_key(param, 0x5a);
+            let a = DhContext::from_private_key(param, &x).unwrap();
+            let b = DhContext::from_private_key(param, &x).unwrap();
+            let mut ya = [0u8; MAX_PUBLIC_KEY_LEN];
+            let mut yb = [0u8; MAX_PUBLIC_KEY_LEN];
+            assert_eq!(
+                a.compute_public_key(&mut ya[..]).unwrap(),
+                b.compute_public_key(&mut yb[..]).unwrap()
+            );
+        }
+    }
+
+    // Independent known-answer vectors for the real FFDHE groups. The public
+    // keys `y` and the shared secret `Z` were computed with a reference modexp
+    // (`pow(g, x, p)` in Python) from the fixed private exponents below, so
+    // these assertions catch a systematic modexp/Montgomery error that a mere
+    // `z_alice == z_bob` round-trip would not. The same two exponents are used
+    // for every group.
+    const KAT_X_ALICE: &str =
+        "03a1f5c9d2b4e6f8071a3c5e7090b2d4f617385a9cbedf0123456789abcdef01";
+    const KAT_X_BOB: &str =
+        "5fedcba9876543210f1e2d3c4b5a69788796a5b4c3d2e1f00112233445566771";
+
+    fn check_ffdhe_kat(param: &'static DhParam, y_alice: &str, y_bob: &str, z: &str) {
+        let x_alice = from_hex(KAT_X_ALICE).unwrap();
+        let x_bob = from_hex(KAT_X_BOB).unwrap();
+        let alice = DhContext::from_private_key(param, &x_alice).unwrap();
+        let bob = DhContext::from_private_key(param, &x_bob).unwrap();
+
+        let mut ya_buf = [0u8; MAX_PUBLIC_KEY_LEN];
+        let mut yb_buf = [0u8; MAX_PUBLIC_KEY_LEN];
+        let ya = alice.compute_public_key(&mut ya_buf[..]).unwrap();
+        let yb = bob.compute_public_key(&mut yb_buf[..]).unwrap();
+        assert!(be_eq(ya, &from_hex(y_alice).unwrap()));
+        assert!(be_eq(yb, &from_hex(y_bob).unwrap()));
+
+        let z_alice = alice.compute_shared_key(yb).unwrap();
+        let z_bob = bob.compute_shared_key(ya).unwrap();
+        assert!(be_eq(z_alice.as_slice(), &from_hex(z).unwrap()));
+        assert!(be_eq(z_bob.as_slice(), &from_hex(z).unwrap()));
+    }
+
+    #[test]
+    fn test_known_answer_ffdhe2048() {
+        check_ffdhe_kat(
+            &DHPARAM_FFDHE2048,
+            "9cd76ba082328dd0f71ad59d02e9a161a6aeb3c4240dd93f8df6c4da299dcfd4\
+             329e7796badaa830c441650451d08530c47fc029c9d8dff223bdd0d26895d18f\
+             564d36166624bc003f0c7965594367668da1790dd091bb5161f8e1735bfbfc54\
+             de1a8cb53c3c3ddd68c28870ac29d5a626d45520601d2cf6b4d76308953601fd\
+             3fe814448937d057720d254c89e05aa7d389cf0af10b268e69f3fc11aea32a3f\
+             8eefcd1bd5696463ef843589b1a171424b47dd3a34fc7b2e3c7fa75458a6a4dd\
+             b3536386e7cf19c2c6b6e5cdf15cec40718e1554d0875c7f8d5879903e5b2026\
+             7bafe646be6c9a8acec13832266fa44e1514b28b5340650538caefe41763494f",
+            "c0ecd071ada034c3b85ed2ba5e34069f95c5550d1151918a9cfec811a7c901c4\
+             cda9c65f4b3672fed399261d35eac0fc2728138e6bd091fcdd858199cba97571\
+             c8ea24e6893cf3b327efa180b529779835dd755750f5a83e1c951154e5a481cc\
+             6b83d4e8c8e4c170e2ea2f42ab319607231fc0e79c0b8fedb216966a60a8d2ec\
+             bc144b5c8ddd99213af7dd1b324ac94c1f89e1ee40aafd59e162cbe96afb31e4\
+             018ccd3a52e37548c48c52eb67805e2f646826052a6ade8f1c942f759ea35350\
+             28a5204a86f2e2adb10f9348bdcb9c297269a6ee9031256916df40230eda4faf\
+             ae8f8e1a0caa9791b40f22255581cb3d3232705ab851f2691c5b4b50cc3da155",
+            "198db451b397adb1a9fac8311db392820d48fa3d7a42a5a9ace2390a9ac68360\
+             3b4cac65c030b4859c17418dabbabe4014e89a1b7a5b2f44c694423fe77f197b\
+             7d7eac1dbfb6ec51ea20402f0ef2720f2bd8a3005026b5d15c450b10e39a9c8f\
+             7da428a03f4188a42cd90eea4fd3d19ea62665fcb8282994929103c7961d2a7a\
+             a6bd9ee7f6dbbcc60dd55628609d0701badfed8fa1ae2085964d76861dc92cdf\
+             41b6d0a5f5859e2e4f9fa065729ed4e96e6924807a25a0c9cb467880823ebea5\
+             9127de72cf1f213bb4a578ec22ebf1f8b759e1be542a4587d70c28138ad6bc89\
+             6a813cd04f786104de97a42e37a18726bcc9a4067947b83c478a58e5b865d849",
+        );
+    }
+
+    #[test]
+    fn test_known_answer_ffdhe3072() {
+        check_ffdhe_kat(
+            &DHPARAM_FFDHE3072,
+            "04ee04e080fce995456884256fd87472aa2e06b9e19ad4a17998036787f7e8c93\
+             743d00b6988bd61c298e733d6c62dafaf0c60a571a470d9019a90532b8831877\
+             85d74dbc87b11e3d021db6ab2d91f1e3e7e76d6de7f3682168bfbdb22cd80c43\
+             77a6890ec4c7dc5435771ce0a07a7fe69962d18f66c7a554df6208c03e5e7a5a\
+             32ee08632d57c607f9d72769b0a5b094463a26857e0617dce84bd28700c76f46\
+             fb0e90e8552795bf0aeafb6df11a5ebf2750bb1e32fddd6f9b3739222bdfa5bb\
+             e104cd8207d1514eb7c91f30674625101292b243458eee3be6b0747a4a4062c8\
+             27828960675a972666d5fadce80fd68b74aeabec114b262da96c60c67512c9af\
+             c8c5039381c2bd0b4a4702443fb814efa16e9da5279ec6fd3471ff3e6d7fbf8f\
+             bf2c4250db6148b602b1f0f85d1e16a6acc784b8b25fc73e7b095cd47dbbe875\
+             fd37107e5a0cc3fa75b3719f627a4be8954570c41885158b08da7f5360683bd1\
+             0242c5f87892d3eb182f3f369e041dc43cae9c23e97fc079629a9c2f0a15520",
+            "347b5b41153b5d84812d53bf2d4db66aba3724b030e0f129349fd1ef35b92c1f\
+             3673ce11144e1efdfa8ebcef8950a0b7434b6ce475cdfc72aed02408b7681497\
+             aebe7e4d26d8688462c349b2025d8ce91f45d9ae86893aea2d29f94aa2bccf65\
+             4aa8de4c7915c77026531179253fb96dcc002bd76b061a73c14e04470b344954\
+             ce390c56182ba45139ffdf98158c32a207b120fdad9b3e03fbc2b79330eed97d\
+             94d2acc54308bfc23a60457c2dcee75b1f746a9195b7cf92c31b0cbbb6b07cb8\
+             11ff1fa2ce6b8941568a95da361b3fbe7bffd56416d49abd48aeaa5cc48d2db8\
+             683e989cd05b826ab12e02b51ba2ca5dbaab62bfcea248a39d75e0338170806f\
+             89da2177c65f1a018a0c2f46c78befcab7988e5dea4ef1deba73aa5db8edce16\
+             c3e23ffb3d581d9672822f7537770d2423cd415a2dacf567c4e505164356182e\
+             c595061fe5239f9bf3893b3aa27bbcdf54370d210110aec340e9590ef6cdf714\
+             d5aa932b486b426c1d409126e217742c891f91bd18603ca3d4819f3338a97b22",
+            "604e43738d4f0fdb35193055a8797eff3a10a8a2ae670142e68d2cf3f41f3019\
+             e677d6f4a2132846df3f4661c67bd72ae36455d8bd4b86a1dbdcbe6465dc2284\
+             f0f07bbf38ef36bcd73bd6ae943037d10edbb6698bed534e177df4d933586a34\
+             8651b29ef36190b05aa9d8debfb6172bb3696bd48917522c288e6ac6acad5cbe\
+             468bbb2a7844f734e846a6b55243f83526183fa2e55ac177a47707a73584ee01\
+             54b018be124069891dbe7db45b3b55ce0b986ab25be0ad2dcc08438024b029c7\
+             5931d71e9c0bf2347a899caff782612d503ee2c89b852e9bf66eaa4a4662c89e\
+             837fb98130357041897d8297c0951c34b125d2abed8171af6c140c1bd0881bd5\
+             4f885eb67783ad1e498bd88d41e506be0b1ef61ab0df51d567f6a122b258d9f6\
+             fb8e1e1e427ed4d0392dbd2e3e745e3ff5a220d49799a6cf21b48894c6edf86c\
+             b764eda726f20c73209dd406508fe4e0e4531c0000dbcd145c51bc0ab4581d7d\
+             4a8b6f5dff6074764ce46d0c36d9846dcbb967ec1607ccd1727041a758aa5e3e",
+        );
+    }
+
+    // Compare a (possibly zero-padded) big-endian output against an expected
+    // magnitude, ignoring leading zero octets.
+    fn be_eq(actual: &[u8], expected: &[u8]) -> bool {
+        super::trim_be(actual) == expected
+    }
+
+    // A genuine known-answer test over a small safe-prime group so the expected
+    // `y` and `Z` can be computed independently of this implementation:
+    //   p = 23 = 2*11 + 1 (safe prime), q = 11, g = 2 (a quadratic residue, so
+    //   it generates the order-11 subgroup). With x_alice = 3, x_bob = 5:
+    //     y_alice = 2^3 mod 23 = 8
+    //     y_bob   = 2^5 mod 23 = 9
+    //     Z       = 9^3 mod 23 = 8^5 mod 23 = 16
+    // Asserting the literal bytes catches a systematic modexp/Montgomery error
+    // that a mere `z_alice == z_bob` round-trip would let through.
+    #[test]
+    fn test_known_answer_vectors() {
+        static P23: DhParam = DhParam { p: "17", g: "02", secret_len: 1, name: "test-sg23" };
+
+        let alice = DhContext::from_private_key(&P23, &[3]).unwrap();
+        let bob = DhContext::from_private_key(&P23, &[5]).unwrap();
+
+        let mut ya_buf = [0u8; MAX_PUBLIC_KEY_LEN];
+        let mut yb_buf = [0u8; MAX_PUBLIC_KEY_LEN];
+        let y_alice = alice.compute_public_key(&mut ya_buf[..]).unwrap();
+        let y_bob = bob.compute_public_key(&mut yb_buf[..]).unwrap();
+        assert!(be_eq(y_alice, &[0x08]));
+        assert!(be_eq(y_bob, &[0x09]));
+
+        let z = alice.compute_shared_key(y_bob).unwrap();
+        assert!(be_eq(z.as_slice(), &[0x10]));
+    }
+
+    #[test]
+    fn test_runtime_param_validate() {
+        let p = from_hex(DHPARAM_FFDHE2048.p).unwrap();
+        let g = from_hex(DHPARAM_FFDHE2048.g).unwrap();
+        let param = DhParamBytes::from_be_bytes(&p, &g, DHPARAM_FFDHE2048.secret_len);
+        let rng = rand::SystemRandom::new();
+        // The FFDHE2048 group is a 2048-bit safe prime with g = 2.
+        param.validate(2048, &rng).unwrap();
+        // An over-strict minimum bit length is rejected.
+        assert_eq!(param.validate(4096, &rng), Err(Error::InvalidParameter));
+        // A small composite is rejected by the primality test.
+        let bad = DhParamBytes::from_be_bytes(&[0x0f], &[2], 2);
+        assert!(bad.validate(0, &rng).is_err());
+    }
+
+    #[test]
+    fn test_runtime_param_agreement() {
+        let p = from_hex(DHPARAM_FFDHE2048.p).unwrap();
+        let g = from_hex(DHPARAM_FFDHE2048.g).unwrap();
+        let param = DhParamBytes::from_be_bytes(&p, &g, DHPARAM_FFDHE2048.secret_len);
+        let rng = rand::SystemRandom::new();
+        let alice = DhContext::from_param_bytes(&param, &rng).unwrap();
+        let bob = DhContext::from_param_bytes(&param, &rng).unwrap();
+
+        let mut ya_buf = [0u8; MAX_PUBLIC_KEY_LEN];
+        let mut yb_buf = [0u8; MAX_PUBLIC_KEY_LEN];
+        let y_alice = alice.compute_public_key(&mut ya_buf[..]).unwrap();
+        let y_bob = bob.compute_public_key(&mut yb_buf[..]).unwrap();
+
+        let z_alice = alice.compute_shared_key(y_bob).unwrap();
+        let z_bob = bob.compute_shared_key(y_alice).unwrap();
+        assert_eq!(z_alice.as_slice(), z_bob.as_slice());
+    }
 }